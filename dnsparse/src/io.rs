@@ -0,0 +1,54 @@
+//! A minimal `no_std` byte-sink trait for writing without allocating.
+
+/// A destination that bytes can be written into without allocating.
+pub trait Sink {
+  /// The error produced when `buf` doesn't fit.
+  type Error;
+
+  /// Writes the whole of `buf`, or fails without writing any of it.
+  fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// The fixed buffer is full; `buf` didn't fit in the remaining space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferTooSmall;
+
+impl Sink for &mut [u8] {
+  type Error = BufferTooSmall;
+
+  fn write_all(&mut self, buf: &[u8]) -> Result<(), BufferTooSmall> {
+    if buf.len() > self.len() {
+      return Err(BufferTooSmall)
+    }
+
+    let (head, tail) = core::mem::take(self).split_at_mut(buf.len());
+    head.copy_from_slice(buf);
+    *self = tail;
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn write_all_advances_through_the_buffer() {
+    let mut storage = [0u8; 4];
+    let mut sink: &mut [u8] = &mut storage;
+
+    sink.write_all(&[1, 2]).unwrap();
+    sink.write_all(&[3, 4]).unwrap();
+
+    assert_eq!(storage, [1, 2, 3, 4]);
+  }
+
+  #[test]
+  fn write_all_rejects_a_write_that_does_not_fit() {
+    let mut storage = [0u8; 2];
+    let mut sink: &mut [u8] = &mut storage;
+
+    assert_eq!(sink.write_all(&[1, 2, 3]), Err(BufferTooSmall));
+  }
+}
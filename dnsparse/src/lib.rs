@@ -0,0 +1,211 @@
+//! Minimal `no_std` DNS message parsing and serialization.
+//!
+//! Written for the captive-portal use case: answer every query on the
+//! soft-AP with "this IP", without pulling in a full resolver.
+
+#![no_std]
+
+extern crate alloc;
+
+mod io;
+mod question;
+mod response;
+
+pub use io::{BufferTooSmall, Sink};
+pub use question::{MalformedName, NameWriteError, Question, QuestionName, Questions};
+pub use response::{DnsResponseBuilder, ResponseError};
+
+/// Length in bytes of the fixed DNS message header that precedes the
+/// question section.
+pub(crate) const HEADER_LEN: usize = 12;
+
+/// A parsed DNS message.
+pub struct DnsFrame<'a> {
+  buf: &'a [u8],
+}
+
+impl<'a> DnsFrame<'a> {
+  pub fn new(buf: &'a [u8]) -> Self {
+    DnsFrame { buf }
+  }
+
+  /// The transaction ID from the header, or 0 if `buf` is too short to
+  /// contain one.
+  pub fn id(&self) -> u16 {
+    if self.buf.len() < 2 {
+      return 0
+    }
+
+    u16::from_be_bytes([self.byte(0), self.byte(1)])
+  }
+
+  /// The number of questions in the message (QDCOUNT), or 0 if `buf` is too
+  /// short to contain a header.
+  pub fn question_count(&self) -> usize {
+    if self.buf.len() < HEADER_LEN {
+      return 0
+    }
+
+    u16::from_be_bytes([self.byte(4), self.byte(5)]) as usize
+  }
+
+  /// An iterator over the [`Question`]s contained in the message.
+  ///
+  /// Yields nothing if `buf` is shorter than the fixed header, rather than
+  /// panicking on a truncated or malicious packet.
+  pub fn questions(&self) -> Questions<'a> {
+    Questions {
+      question_count: self.question_count(),
+      current_question: 0,
+      buf: self.buf,
+      buf_i: HEADER_LEN,
+    }
+  }
+
+  pub fn as_bytes(&self) -> &'a [u8] {
+    self.buf
+  }
+
+  fn byte(&self, i: usize) -> u8 {
+    self.buf.get(i).copied().unwrap_or(0)
+  }
+}
+
+/// The record type of a [`Question`] or answer resource record.
+///
+/// Only the types the captive portal and its clients actually see are
+/// named; everything else round-trips through [`Unknown`](QueryKind::Unknown).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryKind {
+  A,
+  AAAA,
+  CNAME,
+  NS,
+  SOA,
+  PTR,
+  TXT,
+  MX,
+  OPT,
+  Unknown(u16),
+}
+
+impl From<u16> for QueryKind {
+  fn from(value: u16) -> Self {
+    match value {
+      1 => QueryKind::A,
+      2 => QueryKind::NS,
+      5 => QueryKind::CNAME,
+      6 => QueryKind::SOA,
+      12 => QueryKind::PTR,
+      15 => QueryKind::MX,
+      16 => QueryKind::TXT,
+      28 => QueryKind::AAAA,
+      41 => QueryKind::OPT,
+      other => QueryKind::Unknown(other),
+    }
+  }
+}
+
+impl From<QueryKind> for u16 {
+  fn from(kind: QueryKind) -> Self {
+    match kind {
+      QueryKind::A => 1,
+      QueryKind::NS => 2,
+      QueryKind::CNAME => 5,
+      QueryKind::SOA => 6,
+      QueryKind::PTR => 12,
+      QueryKind::MX => 15,
+      QueryKind::TXT => 16,
+      QueryKind::AAAA => 28,
+      QueryKind::OPT => 41,
+      QueryKind::Unknown(value) => value,
+    }
+  }
+}
+
+/// The class of a [`Question`] or answer resource record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryClass {
+  IN,
+  Unknown(u16),
+}
+
+impl From<u16> for QueryClass {
+  fn from(value: u16) -> Self {
+    match value {
+      1 => QueryClass::IN,
+      other => QueryClass::Unknown(other),
+    }
+  }
+}
+
+impl From<QueryClass> for u16 {
+  fn from(class: QueryClass) -> Self {
+    match class {
+      QueryClass::IN => 1,
+      QueryClass::Unknown(value) => value,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // A single-question message asking for `example.com` with the given
+  // QTYPE, QCLASS IN.
+  fn question_buf(kind: u16) -> alloc::vec::Vec<u8> {
+    let mut buf = alloc::vec::Vec::new();
+
+    buf.extend_from_slice(&[0, 0]); // ID
+    buf.extend_from_slice(&[0, 0]); // flags
+    buf.extend_from_slice(&1u16.to_be_bytes()); // QDCOUNT
+    buf.extend_from_slice(&[0, 0]); // ANCOUNT
+    buf.extend_from_slice(&[0, 0]); // NSCOUNT
+    buf.extend_from_slice(&[0, 0]); // ARCOUNT
+
+    buf.push(7);
+    buf.extend_from_slice(b"example");
+    buf.push(3);
+    buf.extend_from_slice(b"com");
+    buf.push(0);
+
+    buf.extend_from_slice(&kind.to_be_bytes());
+    buf.extend_from_slice(&1u16.to_be_bytes()); // CLASS IN
+
+    buf
+  }
+
+  fn round_trip(kind_code: u16, expected: QueryKind) {
+    let buf = question_buf(kind_code);
+    let frame = DnsFrame::new(&buf);
+    let question = frame.questions().next().unwrap();
+
+    assert_eq!(question.kind(), expected);
+  }
+
+  #[test]
+  fn query_kind_round_trips() {
+    round_trip(1, QueryKind::A);
+    round_trip(2, QueryKind::NS);
+    round_trip(5, QueryKind::CNAME);
+    round_trip(6, QueryKind::SOA);
+    round_trip(12, QueryKind::PTR);
+    round_trip(15, QueryKind::MX);
+    round_trip(16, QueryKind::TXT);
+    round_trip(28, QueryKind::AAAA);
+    round_trip(41, QueryKind::OPT);
+    round_trip(1234, QueryKind::Unknown(1234));
+  }
+
+  #[test]
+  fn short_buffers_yield_no_questions_instead_of_panicking() {
+    for buf in [&[][..], &[0u8][..], &[0, 0, 0, 0, 0][..]] {
+      let frame = DnsFrame::new(buf);
+
+      assert_eq!(frame.id(), 0);
+      assert_eq!(frame.question_count(), 0);
+      assert!(frame.questions().next().is_none());
+    }
+  }
+}
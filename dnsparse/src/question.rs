@@ -1,8 +1,7 @@
 use core::fmt;
-use core::mem::size_of;
 use core::str;
 
-use crate::{QueryKind, QueryClass};
+use crate::{QueryKind, QueryClass, Sink};
 
 /// A DNS question.
 #[repr(C)]
@@ -38,59 +37,129 @@ const fn mask_len(len: u8) -> usize {
   (len & 0b00111111) as usize
 }
 
-impl fmt::Display for QuestionName<'_> {
-  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    let mut i = self.start;
+/// A name failed to decode: too many compression-pointer jumps, a pointer
+/// that didn't point strictly backwards, or a truncated label/pointer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MalformedName;
+
+/// Walks the label chain of a name, following compression pointers.
+///
+/// Caps the number of jumps at `buf.len() / 2 + 1` and requires each
+/// pointer to target strictly before its own offset, so a self-referential
+/// or forward-cycling pointer errors out instead of looping.
+struct Labels<'a> {
+  buf: &'a [u8],
+  i: usize,
+  jumps: usize,
+  max_jumps: usize,
+  done: bool,
+}
 
-    loop {
-      let pointer_or_len = self.buf[i];
+impl<'a> Labels<'a> {
+  fn new(buf: &'a [u8], start: usize) -> Self {
+    Labels { buf, i: start, jumps: 0, max_jumps: buf.len() / 2 + 1, done: false }
+  }
+}
 
-      let len = mask_len(pointer_or_len);
+impl<'a> Iterator for Labels<'a> {
+  type Item = Result<&'a [u8], MalformedName>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.done {
+      return None
+    }
+
+    loop {
+      let pointer_or_len = match self.buf.get(self.i) {
+        Some(&b) => b,
+        None => {
+          self.done = true;
+          return Some(Err(MalformedName))
+        }
+      };
 
       if is_pointer(pointer_or_len) {
-        i = (len << 8) + self.buf[i + 1] as usize;
+        self.jumps += 1;
+        if self.jumps > self.max_jumps {
+          self.done = true;
+          return Some(Err(MalformedName))
+        }
+
+        let target = match self.buf.get(self.i + 1) {
+          Some(&low) => (mask_len(pointer_or_len) << 8) + low as usize,
+          None => {
+            self.done = true;
+            return Some(Err(MalformedName))
+          }
+        };
+
+        // RFC 1035 §4.1.4: a pointer must point backwards, which also rules
+        // out the self-referential and forward-cycling loops that would
+        // otherwise run forever.
+        if target >= self.i {
+          self.done = true;
+          return Some(Err(MalformedName))
+        }
+
+        self.i = target;
         continue;
       }
 
+      let len = mask_len(pointer_or_len);
+
       if len == 0 {
-        return Ok(())
+        self.done = true;
+        return None
       }
 
-      if i != self.start {
+      let label_start = self.i + 1;
+
+      let label = match self.buf.get(label_start..(label_start + len)) {
+        Some(label) => label,
+        None => {
+          self.done = true;
+          return Some(Err(MalformedName))
+        }
+      };
+
+      self.i = label_start + len;
+
+      return Some(Ok(label))
+    }
+  }
+}
+
+impl fmt::Display for QuestionName<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (n, label) in Labels::new(self.buf, self.start).enumerate() {
+      let label = match label {
+        Ok(label) => label,
+        Err(MalformedName) => break,
+      };
+
+      if n != 0 {
         ".".fmt(f)?;
       }
 
-      i += 1;
-
-      let s = unsafe { str::from_utf8_unchecked(&self.buf[i..(i + len)]) };
+      let s = unsafe { str::from_utf8_unchecked(label) };
 
       s.fmt(f)?;
-
-      i += len;
     }
+
+    Ok(())
   }
 }
 
 impl PartialEq<&str> for QuestionName<'_> {
   fn eq(&self, other: &&str) -> bool {
-    let mut i = self.start;
-    let mut other_i = 0;
-
     let other = other.as_bytes();
+    let mut other_i = 0;
 
-    loop {
-      let pointer_or_len = self.buf[i];
-
-      let len = mask_len(pointer_or_len);
-
-      if is_pointer(pointer_or_len) {
-        i = (len << 8) + self.buf[i + 1] as usize;
-        continue;
-      }
-
-      if len == 0 {
-        return other_i == other.len()
-      }
+    for label in Labels::new(self.buf, self.start) {
+      let label = match label {
+        Ok(label) => label,
+        Err(MalformedName) => return false,
+      };
 
       if other_i != 0 {
         if other.get(other_i) != Some(&b'.') {
@@ -100,20 +169,60 @@ impl PartialEq<&str> for QuestionName<'_> {
         }
       }
 
-      i += 1;
-
-      if let Some(substring) = other.get(other_i..(other_i + len)) {
-        if !self.buf[i..(i + len)].eq_ignore_ascii_case(substring) {
-          return false
+      match other.get(other_i..(other_i + label.len())) {
+        Some(substring) if label.eq_ignore_ascii_case(substring) => {
+          other_i += label.len();
         }
-      } else {
-        return false
+        _ => return false,
       }
+    }
+
+    other_i == other.len()
+  }
+}
+
+impl QuestionName<'_> {
+  /// Fallible counterpart to the `Display` impl that validates the
+  /// compression-pointer chain and renders to an owned `String`, returning
+  /// `Err(MalformedName)` where `Display` silently stops at the bad label.
+  pub fn try_to_string(&self) -> Result<alloc::string::String, MalformedName> {
+    use alloc::string::String;
+
+    let mut out = String::new();
+
+    for (n, label) in Labels::new(self.buf, self.start).enumerate() {
+      let label = label?;
 
-      i += len;
-      other_i += len;
+      if n != 0 {
+        out.push('.');
+      }
+
+      out.push_str(unsafe { str::from_utf8_unchecked(label) });
     }
+
+    Ok(out)
   }
+
+  /// Re-serializes the name into wire format — length-prefixed labels
+  /// terminated by a zero byte — writing it into `sink`.
+  pub fn write_to<S: Sink>(&self, sink: &mut S) -> Result<(), NameWriteError<S::Error>> {
+    for label in Labels::new(self.buf, self.start) {
+      let label = label.map_err(NameWriteError::Malformed)?;
+
+      sink.write_all(&[label.len() as u8]).map_err(NameWriteError::Sink)?;
+      sink.write_all(label).map_err(NameWriteError::Sink)?;
+    }
+
+    sink.write_all(&[0]).map_err(NameWriteError::Sink)
+  }
+}
+
+/// Error from [`QuestionName::write_to`]: either the name itself was
+/// malformed, or the sink rejected the bytes.
+#[derive(Debug)]
+pub enum NameWriteError<E> {
+  Malformed(MalformedName),
+  Sink(E),
 }
 
 impl<'a> Question<'a> {
@@ -121,6 +230,17 @@ impl<'a> Question<'a> {
     QuestionName { buf: self.buf, start: self.start, end: self.end - 5 }
   }
 
+  /// Fallible counterpart to [`name`](Self::name) that walks the whole
+  /// compression-pointer chain up front, returning `Err(MalformedName)` for
+  /// a packet crafted to loop or panic instead of trusting it.
+  pub fn try_name(&self) -> Result<QuestionName<'a>, MalformedName> {
+    for label in Labels::new(self.buf, self.start) {
+      label?;
+    }
+
+    Ok(self.name())
+  }
+
   pub fn kind(&self) -> QueryKind {
     let b0 = self.end - 4;
     let b1 = b0 + 1;
@@ -178,10 +298,16 @@ fn read_label(buf: &[u8], i: &mut usize) -> Option<bool> {
   }
 }
 
+// QTYPE and QCLASS are each a 2-byte field on the wire; that's independent
+// of the in-memory size of the `QueryKind`/`QueryClass` enums we decode them
+// into, so it's spelled out here rather than taken from `size_of`.
+const QTYPE_LEN: usize = 2;
+const QCLASS_LEN: usize = 2;
+
 #[inline]
 fn read_query_class_and_kind(buf: &[u8], i: &mut usize) -> bool {
-  if *i + size_of::<QueryClass>() + size_of::<QueryKind>() <= buf.len() {
-    *i += size_of::<QueryClass>() + size_of::<QueryKind>();
+  if *i + QCLASS_LEN + QTYPE_LEN <= buf.len() {
+    *i += QCLASS_LEN + QTYPE_LEN;
     true
   } else {
     false
@@ -198,7 +324,13 @@ impl<'a> Iterator for Questions<'a> {
 
     let mut i = self.buf_i;
 
-    assert!(read_question(&self.buf, &mut i));
+    if !read_question(&self.buf, &mut i) {
+      // Stop cleanly on a truncated/malformed question rather than
+      // asserting: a hostile packet shouldn't be able to panic the device.
+      self.current_question = self.question_count;
+      return None
+    }
+
     let question = Question { buf: &self.buf, start: self.buf_i, end: i };
 
     self.current_question += 1;
@@ -208,3 +340,96 @@ impl<'a> Iterator for Questions<'a> {
   }
 }
 
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{BufferTooSmall, DnsFrame};
+
+  // header(12) + a name that is just a pointer back to itself + QTYPE/QCLASS.
+  fn question_with_self_referential_pointer() -> [u8; 18] {
+    let mut buf = [0u8; 18];
+    buf[4] = 0; // QDCOUNT = 1
+    buf[5] = 1;
+    buf[12] = 0xC0; // pointer, target high bits
+    buf[13] = 12; // target == the pointer's own offset
+    buf[16] = 0; // QCLASS IN
+    buf[17] = 1;
+    buf
+  }
+
+  // header(12) + a name that is a pointer forward into the QTYPE/QCLASS
+  // bytes that follow it, rather than backward into already-read data.
+  fn question_with_forward_pointer() -> [u8; 18] {
+    let mut buf = [0u8; 18];
+    buf[4] = 0; // QDCOUNT = 1
+    buf[5] = 1;
+    buf[12] = 0xC0;
+    buf[13] = 14; // target > the pointer's own offset (12)
+    buf[16] = 0; // QCLASS IN
+    buf[17] = 1;
+    buf
+  }
+
+  #[test]
+  fn try_name_rejects_self_referential_pointer() {
+    let buf = question_with_self_referential_pointer();
+    let question = DnsFrame::new(&buf).questions().next().unwrap();
+
+    assert!(matches!(question.try_name(), Err(MalformedName)));
+  }
+
+  #[test]
+  fn try_name_rejects_forward_pointer() {
+    let buf = question_with_forward_pointer();
+    let question = DnsFrame::new(&buf).questions().next().unwrap();
+
+    assert!(matches!(question.try_name(), Err(MalformedName)));
+  }
+
+  #[test]
+  fn questions_iterator_stops_cleanly_on_truncated_pointer() {
+    // A pointer with no second byte to follow: `read_label` can't tell
+    // where it points, so the question itself fails to parse.
+    let buf = [0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0xC0];
+    let mut questions = DnsFrame::new(&buf).questions();
+
+    assert!(questions.next().is_none());
+  }
+
+  // header(12) + a single "abc" label + terminator + QTYPE/QCLASS.
+  fn question_with_short_name() -> [u8; 21] {
+    let mut buf = [0u8; 21];
+    buf[5] = 1; // QDCOUNT = 1
+    buf[12] = 3;
+    buf[13..16].copy_from_slice(b"abc");
+    buf[16] = 0; // name terminator
+    buf[20] = 1; // QCLASS IN
+    buf
+  }
+
+  #[test]
+  fn write_to_re_emits_the_name_in_wire_format() {
+    let buf = question_with_short_name();
+    let question = DnsFrame::new(&buf).questions().next().unwrap();
+    let name = question.try_name().unwrap();
+
+    let mut out = [0u8; 5];
+    let mut sink: &mut [u8] = &mut out;
+    name.write_to(&mut sink).unwrap();
+
+    assert_eq!(out, [3, b'a', b'b', b'c', 0]);
+  }
+
+  #[test]
+  fn write_to_reports_when_the_sink_is_too_small() {
+    let buf = question_with_short_name();
+    let question = DnsFrame::new(&buf).questions().next().unwrap();
+    let name = question.try_name().unwrap();
+
+    let mut out = [0u8; 2];
+    let mut sink: &mut [u8] = &mut out;
+
+    assert!(matches!(name.write_to(&mut sink), Err(NameWriteError::Sink(BufferTooSmall))));
+  }
+}
+
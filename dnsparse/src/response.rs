@@ -0,0 +1,212 @@
+use crate::{DnsFrame, HEADER_LEN};
+
+/// The offset, as a compression pointer, of the first question in a DNS
+/// message body. Answers point back here instead of repeating the name.
+const QUESTION_POINTER: u16 = 0xC000 | (HEADER_LEN as u16);
+
+/// CLASS IN, the only class the captive portal ever answers with.
+const CLASS_IN: u16 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseError {
+  /// `request` did not contain a well-formed question to echo.
+  MalformedRequest,
+  /// `out` isn't large enough to hold the header, question, and answers
+  /// written so far.
+  BufferTooSmall,
+}
+
+/// Builds a DNS response by echoing a request's header ID and first question
+/// and appending answer resource records.
+pub struct DnsResponseBuilder<'a> {
+  out: &'a mut [u8],
+  len: usize,
+}
+
+impl<'a> DnsResponseBuilder<'a> {
+  /// Starts a response to `request` by copying its header and question
+  /// section into `out` and flipping the QR bit to mark it as a reply.
+  ///
+  /// `request` must contain at least one question; only the first is
+  /// echoed, which is all the captive portal ever needs to answer.
+  pub fn new(request: &[u8], out: &'a mut [u8]) -> Result<Self, ResponseError> {
+    if request.len() < HEADER_LEN {
+      return Err(ResponseError::MalformedRequest)
+    }
+
+    let question = DnsFrame::new(request)
+      .questions()
+      .next()
+      .ok_or(ResponseError::MalformedRequest)?;
+
+    let prefix_len = HEADER_LEN + question.as_bytes().len();
+
+    if out.len() < prefix_len {
+      return Err(ResponseError::BufferTooSmall)
+    }
+
+    out[..prefix_len].copy_from_slice(&request[..prefix_len]);
+
+    // Mark as a response (QR bit), force QDCOUNT to 1 since only the first
+    // question is ever echoed, and drop the other counts in favour of what
+    // we're about to write ourselves.
+    out[2] |= 0b1000_0000;
+    out[4..6].copy_from_slice(&1u16.to_be_bytes());
+    out[6..8].copy_from_slice(&0u16.to_be_bytes());
+    out[8..10].copy_from_slice(&0u16.to_be_bytes());
+    out[10..12].copy_from_slice(&0u16.to_be_bytes());
+
+    Ok(DnsResponseBuilder { out, len: prefix_len })
+  }
+
+  /// Appends an A answer record for `ip`.
+  pub fn add_a(&mut self, ttl: u32, ip: [u8; 4]) -> Result<&mut Self, ResponseError> {
+    self.add_answer(crate::QueryKind::A.into(), ttl, &ip)
+  }
+
+  /// Appends an AAAA answer record for `ip`, so IPv6-capable clients on the
+  /// soft-AP get a usable answer instead of an empty response.
+  pub fn add_aaaa(&mut self, ttl: u32, ip: [u8; 16]) -> Result<&mut Self, ResponseError> {
+    self.add_answer(crate::QueryKind::AAAA.into(), ttl, &ip)
+  }
+
+  fn add_answer(&mut self, kind: u16, ttl: u32, rdata: &[u8]) -> Result<&mut Self, ResponseError> {
+    let record_len = 2 + 2 + 2 + 4 + 2 + rdata.len();
+
+    if self.out.len() - self.len < record_len {
+      return Err(ResponseError::BufferTooSmall)
+    }
+
+    let start = self.len;
+    self.write(start, &QUESTION_POINTER.to_be_bytes());
+    self.write(start + 2, &kind.to_be_bytes());
+    self.write(start + 4, &CLASS_IN.to_be_bytes());
+    self.write(start + 6, &ttl.to_be_bytes());
+    self.write(start + 10, &(rdata.len() as u16).to_be_bytes());
+    self.write(start + 12, rdata);
+
+    self.len += record_len;
+    self.bump_answer_count();
+
+    Ok(self)
+  }
+
+  fn write(&mut self, at: usize, bytes: &[u8]) {
+    self.out[at..(at + bytes.len())].copy_from_slice(bytes);
+  }
+
+  fn bump_answer_count(&mut self) {
+    let count = u16::from_be_bytes([self.out[6], self.out[7]]) + 1;
+    self.out[6..8].copy_from_slice(&count.to_be_bytes());
+  }
+
+  /// Finishes the response, returning the bytes written into `out`.
+  pub fn finish(self) -> &'a [u8] {
+    &self.out[..self.len]
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // A request whose header claims QDCOUNT 2 and whose body actually
+  // contains two questions.
+  fn request_with_two_questions() -> alloc::vec::Vec<u8> {
+    let mut buf = alloc::vec::Vec::new();
+
+    buf.extend_from_slice(&[0x12, 0x34]); // ID
+    buf.extend_from_slice(&[0, 0]); // flags
+    buf.extend_from_slice(&2u16.to_be_bytes()); // QDCOUNT
+    buf.extend_from_slice(&[0, 0]); // ANCOUNT
+    buf.extend_from_slice(&[0, 0]); // NSCOUNT
+    buf.extend_from_slice(&[0, 0]); // ARCOUNT
+
+    for name in [&b"one"[..], &b"two"[..]] {
+      buf.push(name.len() as u8);
+      buf.extend_from_slice(name);
+      buf.push(0);
+      buf.extend_from_slice(&1u16.to_be_bytes()); // QTYPE A
+      buf.extend_from_slice(&1u16.to_be_bytes()); // QCLASS IN
+    }
+
+    buf
+  }
+
+  #[test]
+  fn only_echoes_first_question_and_forces_qdcount_to_one() {
+    let request = request_with_two_questions();
+    let mut out = [0u8; 64];
+
+    let response = DnsResponseBuilder::new(&request, &mut out).unwrap().finish();
+
+    assert_eq!(&response[4..6], &1u16.to_be_bytes());
+    assert_eq!(response.len(), HEADER_LEN + 1 + 3 + 1 + 2 + 2);
+  }
+
+  #[test]
+  fn rejects_a_request_with_no_questions() {
+    let mut request = request_with_two_questions();
+    request[4..6].copy_from_slice(&0u16.to_be_bytes());
+
+    let mut out = [0u8; 64];
+
+    assert!(matches!(
+      DnsResponseBuilder::new(&request, &mut out).err(),
+      Some(ResponseError::MalformedRequest)
+    ));
+  }
+
+  #[test]
+  fn rejects_a_request_shorter_than_the_header_instead_of_panicking() {
+    let mut out = [0u8; 64];
+
+    for request in [&[][..], &[0u8][..], &[0, 0, 0, 0, 0][..]] {
+      assert!(matches!(
+        DnsResponseBuilder::new(request, &mut out).err(),
+        Some(ResponseError::MalformedRequest)
+      ));
+    }
+  }
+
+  fn request_with_one_question() -> alloc::vec::Vec<u8> {
+    let mut buf = alloc::vec::Vec::new();
+
+    buf.extend_from_slice(&[0x12, 0x34]); // ID
+    buf.extend_from_slice(&[0, 0]); // flags
+    buf.extend_from_slice(&1u16.to_be_bytes()); // QDCOUNT
+    buf.extend_from_slice(&[0, 0]); // ANCOUNT
+    buf.extend_from_slice(&[0, 0]); // NSCOUNT
+    buf.extend_from_slice(&[0, 0]); // ARCOUNT
+
+    buf.push(3);
+    buf.extend_from_slice(b"one");
+    buf.push(0);
+    buf.extend_from_slice(&28u16.to_be_bytes()); // QTYPE AAAA
+    buf.extend_from_slice(&1u16.to_be_bytes()); // QCLASS IN
+
+    buf
+  }
+
+  #[test]
+  fn add_aaaa_writes_the_rr_layout() {
+    let request = request_with_one_question();
+    let mut out = [0u8; 64];
+
+    let ip = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+    let mut builder = DnsResponseBuilder::new(&request, &mut out).unwrap();
+    builder.add_aaaa(300, ip).unwrap();
+    let response = builder.finish();
+
+    let question_len = HEADER_LEN + 1 + 3 + 1 + 2 + 2;
+    let answer = &response[question_len..];
+
+    assert_eq!(&answer[0..2], &QUESTION_POINTER.to_be_bytes());
+    assert_eq!(&answer[2..4], &28u16.to_be_bytes()); // TYPE AAAA
+    assert_eq!(&answer[4..6], &1u16.to_be_bytes()); // CLASS IN
+    assert_eq!(&answer[6..10], &300u32.to_be_bytes()); // TTL
+    assert_eq!(&answer[10..12], &16u16.to_be_bytes()); // RDLENGTH
+    assert_eq!(&answer[12..28], &ip); // RDATA
+    assert_eq!(&response[6..8], &1u16.to_be_bytes()); // ANCOUNT
+  }
+}